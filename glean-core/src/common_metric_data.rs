@@ -0,0 +1,65 @@
+/// The supported metrics' lifetimes.
+///
+/// A metric's lifetime determines when its stored value gets reset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lifetime {
+    /// The metric is reset with each sent ping.
+    Ping,
+    /// The metric is reset on each application restart.
+    Application,
+    /// The metric is reset only when the user resets their profile.
+    User,
+}
+
+impl Default for Lifetime {
+    fn default() -> Self {
+        Lifetime::Ping
+    }
+}
+
+/// The common set of data shared across all the different metric types.
+#[derive(Debug, Clone, Default)]
+pub struct CommonMetricData {
+    /// The metric's name.
+    pub name: String,
+    /// The metric's category.
+    pub category: String,
+    /// The ping names this metric is sent in.
+    pub send_in_pings: Vec<String>,
+    /// The metric's lifetime.
+    pub lifetime: Lifetime,
+    /// Whether or not the metric is disabled.
+    pub disabled: bool,
+}
+
+impl CommonMetricData {
+    /// Creates a new metadata object.
+    pub fn new<A, B, C>(category: A, name: B, ping_name: C) -> Self
+    where
+        A: Into<String>,
+        B: Into<String>,
+        C: Into<String>,
+    {
+        Self {
+            name: name.into(),
+            category: category.into(),
+            send_in_pings: vec![ping_name.into()],
+            ..Default::default()
+        }
+    }
+
+    /// Overrides the metric's lifetime, returning `self` for chaining.
+    pub fn with_lifetime(mut self, lifetime: Lifetime) -> Self {
+        self.lifetime = lifetime;
+        self
+    }
+
+    /// The identifier used when recording this metric into the data store.
+    pub fn identifier(&self) -> String {
+        if self.category.is_empty() {
+            self.name.clone()
+        } else {
+            format!("{}.{}", self.category, self.name)
+        }
+    }
+}
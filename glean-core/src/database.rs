@@ -0,0 +1,265 @@
+use std::fs;
+use std::path::Path;
+
+use rkv::{Rkv, SingleStore, StoreOptions};
+
+use crate::common_metric_data::Lifetime;
+use crate::metrics::Metric;
+
+/// The database that backs metric storage, keyed by [`Lifetime`].
+///
+/// Each lifetime gets its own `rkv` store so that clearing one (e.g. on
+/// `set_upload_enabled(false)`) never touches the others.
+#[derive(Debug)]
+pub struct Database {
+    rkv: Rkv,
+    ping_lifetime_store: SingleStore,
+    application_lifetime_store: SingleStore,
+    user_lifetime_store: SingleStore,
+}
+
+impl Database {
+    /// Creates or opens the on-disk database rooted at `data_path`.
+    pub fn new(data_path: &str) -> Self {
+        let path = Path::new(data_path).join("db");
+        fs::create_dir_all(&path).expect("Failed to create Glean data directory");
+
+        let rkv = Rkv::new(&path).expect("Failed to open Glean database");
+        let ping_lifetime_store = rkv
+            .open_single("ping_lifetime", StoreOptions::create())
+            .expect("Failed to open ping lifetime store");
+        let application_lifetime_store = rkv
+            .open_single("application_lifetime", StoreOptions::create())
+            .expect("Failed to open application lifetime store");
+        let user_lifetime_store = rkv
+            .open_single("user_lifetime", StoreOptions::create())
+            .expect("Failed to open user lifetime store");
+
+        Self {
+            rkv,
+            ping_lifetime_store,
+            application_lifetime_store,
+            user_lifetime_store,
+        }
+    }
+
+    fn store_for(&self, lifetime: Lifetime) -> SingleStore {
+        match lifetime {
+            Lifetime::Ping => self.ping_lifetime_store,
+            Lifetime::Application => self.application_lifetime_store,
+            Lifetime::User => self.user_lifetime_store,
+        }
+    }
+
+    /// Iterates over all keys in `lifetime`'s store starting with
+    /// `iter_start`, calling `transaction_fn` for each matching entry.
+    pub fn iter_store_from<F>(&self, lifetime: Lifetime, iter_start: &str, mut transaction_fn: F)
+    where
+        F: FnMut(&[u8], &Metric),
+    {
+        let store = self.store_for(lifetime);
+        let reader = self.rkv.read().expect("Failed to start read transaction");
+        let mut iter = store.iter_from(&reader, iter_start).unwrap();
+        while let Some(Ok((key, Some(value)))) = iter.next() {
+            if !key.starts_with(iter_start.as_bytes()) {
+                break;
+            }
+            if let Some(metric) = Metric::from_rkv_value(&value) {
+                transaction_fn(key, &metric);
+            }
+        }
+    }
+
+    /// Runs `transaction_fn` against a writer for `lifetime`'s store.
+    /// The closure is responsible for committing the writer; changes from
+    /// a writer it does not commit are discarded.
+    pub fn write_with_store<F>(&self, lifetime: Lifetime, mut transaction_fn: F)
+    where
+        F: FnMut(rkv::Writer, SingleStore),
+    {
+        let writer = self.rkv.write().expect("Failed to start write transaction");
+        transaction_fn(writer, self.store_for(lifetime));
+    }
+
+    /// Unconditionally stores `metric` under `key`, scoped to `ping_name`.
+    pub fn record(&self, lifetime: Lifetime, ping_name: &str, key: &str, metric: &Metric) {
+        let full_key = format!("{}#{}", ping_name, key);
+        self.write_with_store(lifetime, |mut writer, store| {
+            let mut scratch = String::new();
+            let encoded = metric.to_rkv_value(&mut scratch);
+            store
+                .put(&mut writer, &full_key, &encoded)
+                .expect("Failed to write to the database");
+            writer.commit().expect("Failed to commit write transaction");
+        });
+    }
+
+    /// Reads the current value (if any) for `key`, passes it through
+    /// `transform`, and stores the result.
+    pub fn record_with<F>(&self, lifetime: Lifetime, ping_name: &str, key: &str, transform: F)
+    where
+        F: Fn(Option<Metric>) -> Metric,
+    {
+        let full_key = format!("{}#{}", ping_name, key);
+        self.write_with_store(lifetime, |mut writer, store| {
+            let old_value = store
+                .get(&writer, &full_key)
+                .expect("Failed to read from the database")
+                .and_then(|v| Metric::from_rkv_value(&v));
+            let new_value = transform(old_value);
+            let mut scratch = String::new();
+            let encoded = new_value.to_rkv_value(&mut scratch);
+            store
+                .put(&mut writer, &full_key, &encoded)
+                .expect("Failed to write to the database");
+            writer.commit().expect("Failed to commit write transaction");
+        });
+    }
+
+    /// Drops every entry in `lifetime`'s store, leaving the store itself
+    /// open for further use.
+    pub fn clear_lifetime(&self, lifetime: Lifetime) {
+        self.write_with_store(lifetime, |mut writer, store| {
+            store
+                .clear(&mut writer)
+                .expect("Failed to clear the database store");
+            writer.commit().expect("Failed to commit clear transaction");
+        });
+    }
+
+    /// Drops only the entries belonging to `ping_name` from the
+    /// `Lifetime::Ping` store, e.g. once that ping has been uploaded
+    /// successfully. Other ping-lifetime metrics are left untouched.
+    pub fn clear_ping_lifetime_storage(&self, ping_name: &str) {
+        let prefix = format!("{}#", ping_name);
+        self.write_with_store(Lifetime::Ping, |mut writer, store| {
+            let keys: Vec<Vec<u8>> = {
+                let mut iter = store.iter_from(&writer, &prefix).unwrap();
+                let mut keys = Vec::new();
+                while let Some(Ok((key, Some(_)))) = iter.next() {
+                    if !key.starts_with(prefix.as_bytes()) {
+                        break;
+                    }
+                    keys.push(key.to_vec());
+                }
+                keys
+            };
+
+            for key in keys {
+                store
+                    .delete(&mut writer, &key)
+                    .expect("Failed to delete from the database");
+            }
+            writer.commit().expect("Failed to commit clear transaction");
+        });
+    }
+}
+
+/// A short prefix tag distinguishing the string-shaped [`Metric`] variants so
+/// that reading a value back out of `rkv` (which only knows about
+/// `Bool`/`I64`/`Str`/..., not our own enum) doesn't have to guess.
+const STRING_TAG: &str = "S:";
+const UUID_TAG: &str = "U:";
+
+impl Metric {
+    /// Encodes `self` as an `rkv::Value`, using `scratch` as a backing
+    /// buffer for the tagged-string encoding used by `String`/`Uuid`.
+    fn to_rkv_value<'a>(&'a self, scratch: &'a mut String) -> rkv::Value<'a> {
+        use rkv::Value;
+        match self {
+            Metric::Boolean(b) => Value::Bool(*b),
+            Metric::Counter(c) => Value::I64(i64::from(*c)),
+            Metric::String(s) => {
+                scratch.push_str(STRING_TAG);
+                scratch.push_str(s);
+                Value::Str(scratch)
+            }
+            Metric::Uuid(u) => {
+                scratch.push_str(UUID_TAG);
+                scratch.push_str(u);
+                Value::Str(scratch)
+            }
+        }
+    }
+
+    fn from_rkv_value(value: &rkv::Value) -> Option<Metric> {
+        match value {
+            rkv::Value::Bool(b) => Some(Metric::Boolean(*b)),
+            rkv::Value::I64(i) => Some(Metric::Counter(*i as i32)),
+            rkv::Value::Str(s) => {
+                if let Some(rest) = s.strip_prefix(STRING_TAG) {
+                    Some(Metric::String(rest.to_string()))
+                } else if let Some(rest) = s.strip_prefix(UUID_TAG) {
+                    Some(Metric::Uuid(rest.to_string()))
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_db() -> (tempfile::TempDir, Database) {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let db = Database::new(dir.path().to_str().unwrap());
+        (dir, db)
+    }
+
+    #[test]
+    fn round_trips_uuid_metrics_as_uuid_not_string() {
+        let (_dir, db) = test_db();
+
+        db.record(
+            Lifetime::User,
+            "glean_client_info",
+            "client_id",
+            &Metric::Uuid("deadbeef-0000-0000-0000-000000000000".to_string()),
+        );
+
+        let mut seen = None;
+        db.iter_store_from(Lifetime::User, "glean_client_info#", |_, metric| {
+            seen = Some(metric.clone());
+        });
+
+        assert_eq!(
+            seen,
+            Some(Metric::Uuid("deadbeef-0000-0000-0000-000000000000".to_string()))
+        );
+    }
+
+    #[test]
+    fn round_trips_string_metrics_as_string() {
+        let (_dir, db) = test_db();
+
+        db.record(Lifetime::Ping, "events", "page.title", &Metric::String("home".to_string()));
+
+        let mut seen = None;
+        db.iter_store_from(Lifetime::Ping, "events#", |_, metric| {
+            seen = Some(metric.clone());
+        });
+
+        assert_eq!(seen, Some(Metric::String("home".to_string())));
+    }
+
+    #[test]
+    fn clear_ping_lifetime_storage_only_clears_matching_ping() {
+        let (_dir, db) = test_db();
+
+        db.record(Lifetime::Ping, "metrics", "a.b", &Metric::Counter(1));
+        db.record(Lifetime::Ping, "other", "c.d", &Metric::Counter(2));
+
+        db.clear_ping_lifetime_storage("metrics");
+
+        let mut remaining = Vec::new();
+        db.iter_store_from(Lifetime::Ping, "", |key, _| {
+            remaining.push(String::from_utf8_lossy(key).to_string());
+        });
+
+        assert_eq!(remaining, vec!["other#c.d".to_string()]);
+    }
+}
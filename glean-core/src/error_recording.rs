@@ -0,0 +1,47 @@
+use crate::common_metric_data::CommonMetricData;
+use crate::metrics::Metric;
+use crate::Glean;
+
+/// The type of error recorded for a metric when something goes wrong
+/// while collecting or recording its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorType {
+    /// For when the value to be recorded does not match the metric-specific
+    /// restrictions.
+    InvalidValue,
+    /// For when the label of a labeled metric does not conform to the
+    /// requirements.
+    InvalidLabel,
+    /// For when the metric caller is on the wrong thread.
+    InvalidState,
+    /// For when the value to be recorded overflows the metric-specific upper
+    /// range limit.
+    InvalidOverflow,
+}
+
+impl ErrorType {
+    /// The name of the error, as used in the `error` ping category.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorType::InvalidValue => "invalid_value",
+            ErrorType::InvalidLabel => "invalid_label",
+            ErrorType::InvalidState => "invalid_state",
+            ErrorType::InvalidOverflow => "invalid_overflow",
+        }
+    }
+}
+
+/// Records that `error` occurred while recording the metric described by
+/// `meta`, as a counter alongside the metric's own ping, keyed by both the
+/// error type and the metric's identifier so each offending metric's error
+/// count can be told apart from the others.
+pub(crate) fn record_error(glean: &Glean, meta: &CommonMetricData, error: ErrorType) {
+    let key = format!("glean.error.{}/{}", error.as_str(), meta.identifier());
+    glean.record_with(meta.lifetime, &meta.send_in_pings[0], &key, |old_value| {
+        let old_value = match old_value {
+            Some(Metric::Counter(i)) => i,
+            _ => 0,
+        };
+        Metric::Counter(old_value.saturating_add(1))
+    });
+}
@@ -0,0 +1,18 @@
+use std::fs;
+use std::path::Path;
+
+const FIRST_RUN_MARKER: &str = "first_run.txt";
+
+/// Determine whether this is the first time Glean has run with the given
+/// `data_path`, by checking for (and creating, if missing) a marker file.
+pub fn is_first_run(data_path: &str) -> bool {
+    let marker = Path::new(data_path).join(FIRST_RUN_MARKER);
+    if marker.exists() {
+        return false;
+    }
+
+    // Best-effort: if we can't write the marker we still report this as the
+    // first run, rather than failing initialization.
+    let _ = fs::write(&marker, b"");
+    true
+}
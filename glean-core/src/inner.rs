@@ -0,0 +1,47 @@
+use crate::database::Database;
+use crate::upload::PingUploadManager;
+use crate::Configuration;
+
+/// The private, mutable state backing a [`crate::Glean`] instance.
+///
+/// This is kept behind the `RwLock` in `Glean` so that the public API can
+/// offer interior mutability without exposing `&mut self` methods.
+#[derive(Debug)]
+pub(crate) struct Inner {
+    upload_enabled: bool,
+    application_id: String,
+    channel: Option<String>,
+    pub(crate) data_store: Database,
+    pub(crate) upload_manager: PingUploadManager,
+}
+
+impl Inner {
+    /// Builds the on-disk storage and upload manager for `cfg` and starts
+    /// fully initialized: unlike the old global singleton, a `Glean`
+    /// instance is ready to use as soon as it is constructed.
+    pub(crate) fn new(cfg: &Configuration) -> Self {
+        Self {
+            upload_enabled: cfg.upload_enabled,
+            application_id: cfg.application_id.clone(),
+            channel: cfg.channel.clone(),
+            data_store: Database::new(&cfg.data_path),
+            upload_manager: PingUploadManager::new(&cfg.data_path),
+        }
+    }
+
+    pub(crate) fn set_upload_enabled(&mut self, flag: bool) {
+        self.upload_enabled = flag;
+    }
+
+    pub(crate) fn is_upload_enabled(&self) -> bool {
+        self.upload_enabled
+    }
+
+    pub(crate) fn application_id(&self) -> &str {
+        &self.application_id
+    }
+
+    pub(crate) fn channel(&self) -> Option<&str> {
+        self.channel.as_deref()
+    }
+}
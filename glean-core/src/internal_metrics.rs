@@ -0,0 +1,54 @@
+//! The metrics Glean collects about itself and the application's lifecycle.
+//!
+//! These are not defined via `metrics.yaml` like application metrics, since
+//! they need to be available before any such definitions can be loaded.
+
+use lazy_static::lazy_static;
+
+use crate::metrics::{BooleanMetric, UuidMetric};
+use crate::{CommonMetricData, Lifetime};
+
+lazy_static! {
+    /// Whether this is the first time the application has ever run with
+    /// this `data_path`.
+    pub static ref first_run: BooleanMetric = BooleanMetric::new(CommonMetricData::new(
+        "",
+        "first_run",
+        "glean_client_info",
+    ));
+
+    /// The client's randomly-generated identifier.
+    ///
+    /// This uses `Lifetime::User` so it survives ping submission and is
+    /// only ever reset explicitly, e.g. on opt-out.
+    pub static ref client_id: UuidMetric = UuidMetric::new(
+        CommonMetricData::new(
+            "",
+            "client_id",
+            "glean_client_info",
+        )
+        .with_lifetime(Lifetime::User)
+    );
+
+    /// Set to `true` while the application is running and cleared by a
+    /// clean [`crate::Glean::on_shutdown`]. If it is still `true` the next
+    /// time Glean starts up, the previous session ended uncleanly (e.g. a
+    /// crash), which is reported as a `baseline` ping with reason
+    /// `"dirty_startup"`.
+    ///
+    /// This is recorded under the `"glean_internal_info"` ping name, not
+    /// `"baseline"`: it is bookkeeping Glean uses to decide *whether* to
+    /// send a `baseline` ping, not a metric `StorageManager::snapshot`
+    /// should ever include in one.
+    ///
+    /// Uses `Lifetime::User` so it is never reset out from under us by a
+    /// ping being sent.
+    pub static ref dirty_bit: BooleanMetric = BooleanMetric::new(
+        CommonMetricData::new(
+            "",
+            "dirty_bit",
+            "glean_internal_info",
+        )
+        .with_lifetime(Lifetime::User)
+    );
+}
@@ -1,6 +1,5 @@
-use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::sync::{OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
-use lazy_static::lazy_static;
 use rkv::SingleStore;
 
 mod common_metric_data;
@@ -12,14 +11,45 @@ mod internal_metrics;
 pub mod metrics;
 pub mod ping;
 pub mod storage;
+pub mod upload;
 
 pub use common_metric_data::{CommonMetricData, Lifetime};
 pub use error_recording::ErrorType;
 use inner::Inner;
 use metrics::Metric;
+use ping::{PendingPing, PingMaker};
+pub use upload::{PingUploadTask, UploadResult};
 
-lazy_static! {
-    static ref GLEAN_SINGLETON: Glean = Glean::new();
+/// The `client_id` value written on opt-out, standing in for "no identifier
+/// was submitted by this client". Never used as a real client id.
+const CANARY_CLIENT_ID: &str = "00000000-0000-0000-0000-000000000000";
+
+/// The process-wide instance handed out by the backward-compatibility
+/// [`Glean::singleton`] shim. New code should hold its own [`Glean`]
+/// instance instead.
+static GLEAN_SINGLETON: OnceLock<Glean> = OnceLock::new();
+
+/// The settings a [`Glean`] instance is constructed with.
+///
+/// There is no implicit global state: every field here is required up
+/// front so that an embedder (or a test) can create several independent,
+/// differently-configured instances in the same process.
+#[derive(Debug, Clone)]
+pub struct Configuration {
+    /// The directory Glean stores its database and pending pings under.
+    pub data_path: String,
+    /// The application id, used when building ping submission URLs.
+    pub application_id: String,
+    /// The initial upload-enabled state.
+    pub upload_enabled: bool,
+    /// The maximum number of events buffered before an `events` ping is
+    /// sent. Reserved for the event metric type.
+    pub max_events: Option<usize>,
+    /// The release channel the application is running on (e.g. `"beta"`),
+    /// recorded in the `client_info` of pings assembled by
+    /// [`ping::PingMaker::collect`]. The `deletion-request` ping does not
+    /// carry it.
+    pub channel: Option<String>,
 }
 
 #[derive(Debug)]
@@ -28,39 +58,59 @@ pub struct Glean {
 }
 
 impl Glean {
-    fn new() -> Self {
-        Self {
-            inner: RwLock::new(Inner::new()),
-        }
+    /// Creates a new, independent Glean instance from `cfg`.
+    ///
+    /// This sets up on-disk storage under `cfg.data_path` and initializes
+    /// the core metrics (`client_id`, `first_run`) immediately; there is no
+    /// separate `initialize` step to call afterwards. If the previous
+    /// session never called [`Glean::on_shutdown`] (e.g. it crashed), a
+    /// `baseline` ping with reason `"dirty_startup"` is queued for upload.
+    pub fn new(cfg: Configuration) -> Self {
+        let glean = Self {
+            inner: RwLock::new(Inner::new(&cfg)),
+        };
+        glean.initialize_core_metrics(&cfg.data_path);
+        glean.check_dirty_bit();
+        glean
     }
 
-    /// Get the global singleton instance of Glean.
-    ///
-    /// This is internally used by metrics and for coordinating storage.
+    /// Initializes (on first call) and returns the process-wide singleton
+    /// Glean instance.
     ///
-    /// Use `initialize()` to properly initialize this object.
-    pub fn singleton() -> &'static Glean {
-        &*GLEAN_SINGLETON
+    /// This is a thin shim for embedders that have not yet migrated off of
+    /// global state; new code should hold its own instance from
+    /// [`Glean::new`] instead. Subsequent calls ignore `cfg` and return the
+    /// instance created by the first call.
+    pub fn singleton(cfg: Configuration) -> &'static Glean {
+        GLEAN_SINGLETON.get_or_init(|| Glean::new(cfg))
     }
 
-    /// Initialize the global Glean object.
-    ///
-    /// This will create the necessary directories and files in `data_path`.
-    /// This will also initialize the core metrics.
-    pub fn initialize(&self, data_path: &str) {
-        {
-            let mut inner = self.write();
-            inner.initialize(data_path);
-
-            // drop lock before we call any metric setters
+    fn initialize_core_metrics(&self, data_path: &str) {
+        internal_metrics::first_run.force_set(self, first_run::is_first_run(data_path));
+        internal_metrics::client_id.force_generate_if_missing(self);
+    }
+
+    /// Checks whether the dirty bit was still set from a previous session,
+    /// queues a `dirty_startup` baseline ping if so, then marks the current
+    /// session as dirty until [`Glean::on_shutdown`] is called.
+    fn check_dirty_bit(&self) {
+        if internal_metrics::dirty_bit.get(self).unwrap_or(false) {
+            if let Some(pending) = PingMaker::collect(self, "baseline", Some("dirty_startup")) {
+                self.enqueue_ping(pending);
+            }
         }
 
-        self.initialize_core_metrics(data_path);
+        internal_metrics::dirty_bit.force_set(self, true);
     }
 
-    fn initialize_core_metrics(&self, data_path: &str) {
-        internal_metrics::first_run.set(first_run::is_first_run(data_path));
-        internal_metrics::client_id.generate_if_missing();
+    /// Marks the current session as having shut down cleanly.
+    ///
+    /// The embedder should call this as the last thing it does before the
+    /// application exits; if it is skipped (e.g. due to a crash), the next
+    /// [`Glean::new`] call reports the previous session via a
+    /// `dirty_startup` baseline ping.
+    pub fn on_shutdown(&self) {
+        internal_metrics::dirty_bit.force_set(self, false);
     }
 
     fn read(&self) -> RwLockReadGuard<Inner> {
@@ -71,16 +121,55 @@ impl Glean {
         self.inner.write().unwrap()
     }
 
-    /// Determine whether the global Glean object is fully initialized yet.
-    pub fn is_initialized(&self) -> bool {
-        self.read().is_initialized()
-    }
-
     /// Set whether upload is enabled or not.
     ///
-    /// When upload is disabled, no data will be recorded.
+    /// When upload is disabled, no data will be recorded, any data already
+    /// recorded is deleted, and a `deletion-request` ping is sent so the
+    /// server can purge history collected under the old `client_id`. On
+    /// re-enabling, a fresh `client_id` is generated.
     pub fn set_upload_enabled(&self, flag: bool) {
-        self.write().set_upload_enabled(flag)
+        let was_enabled = self.is_upload_enabled();
+        if was_enabled == flag {
+            return;
+        }
+
+        if !flag {
+            let former_client_id = internal_metrics::client_id
+                .get(self)
+                .unwrap_or_else(|| CANARY_CLIENT_ID.to_string());
+
+            {
+                let inner = self.read();
+                for lifetime in &[Lifetime::Ping, Lifetime::Application, Lifetime::User] {
+                    inner.data_store.clear_lifetime(*lifetime);
+                }
+            }
+
+            internal_metrics::client_id.force_set(
+                self,
+                uuid::Uuid::parse_str(CANARY_CLIENT_ID).expect("canary client id is a valid UUID"),
+            );
+            // `clear_lifetime(Lifetime::User)` above also wiped the dirty
+            // bit, since it shares that lifetime with `client_id`. The
+            // current session is still running (and still dirty until the
+            // next clean `on_shutdown`), so restore it rather than let the
+            // next startup mistake this opt-out for a clean shutdown.
+            internal_metrics::dirty_bit.force_set(self, true);
+
+            let deletion_ping = PingMaker::collect_deletion_request(self, &former_client_id);
+            self.enqueue_ping(deletion_ping);
+        }
+
+        self.write().set_upload_enabled(flag);
+
+        if flag {
+            internal_metrics::client_id.force_generate_and_set(self);
+        }
+    }
+
+    /// Queues an already-assembled ping for upload.
+    fn enqueue_ping(&self, pending: PendingPing) {
+        self.write().upload_manager.enqueue(pending);
     }
 
     /// Determine whether upload is enabled.
@@ -108,13 +197,43 @@ impl Glean {
             .write_with_store(store_name, transaction_fn)
     }
 
+    /// Records `metric`, unless upload is disabled, in which case the
+    /// write is silently dropped so that no data accumulates behind an
+    /// opt-out.
     pub(crate) fn record(&self, lifetime: Lifetime, ping_name: &str, key: &str, metric: &Metric) {
+        if !self.is_upload_enabled() {
+            return;
+        }
+        self.record_unchecked(lifetime, ping_name, key, metric);
+    }
+
+    pub(crate) fn record_with<F>(
+        &self,
+        lifetime: Lifetime,
+        ping_name: &str,
+        key: &str,
+        transform: F,
+    ) where
+        F: Fn(Option<Metric>) -> Metric,
+    {
+        if !self.is_upload_enabled() {
+            return;
+        }
+        self.record_with_unchecked(lifetime, ping_name, key, transform);
+    }
+
+    /// Like [`Glean::record`], but bypasses the `upload_enabled` gate.
+    /// Reserved for Glean's own lifecycle bookkeeping (`first_run`,
+    /// `client_id`, `dirty_bit`), which must persist regardless of the
+    /// user's upload choice.
+    pub(crate) fn record_unchecked(&self, lifetime: Lifetime, ping_name: &str, key: &str, metric: &Metric) {
         self.write()
             .data_store
             .record(lifetime, ping_name, key, metric)
     }
 
-    pub(crate) fn record_with<F>(
+    /// Like [`Glean::record_with`], but bypasses the `upload_enabled` gate.
+    pub(crate) fn record_with_unchecked<F>(
         &self,
         lifetime: Lifetime,
         ping_name: &str,
@@ -127,4 +246,224 @@ impl Glean {
             .data_store
             .record_with(lifetime, ping_name, key, transform)
     }
+
+    /// The application id this instance was initialized with.
+    pub(crate) fn application_id(&self) -> String {
+        self.read().application_id().to_string()
+    }
+
+    /// The release channel this instance was initialized with, if any.
+    pub(crate) fn channel(&self) -> Option<String> {
+        self.read().channel().map(str::to_string)
+    }
+
+    /// Requests the next task the embedder should perform on Glean's
+    /// behalf: upload a ping, wait and ask again, or do nothing.
+    ///
+    /// Actually performing the network request is the embedder's
+    /// responsibility; once it completes, it must report the outcome via
+    /// [`Glean::process_upload_response`].
+    pub fn get_upload_task(&self) -> PingUploadTask {
+        self.write().upload_manager.get_task()
+    }
+
+    /// Reports the outcome of an upload previously handed out via
+    /// [`Glean::get_upload_task`]. If the upload succeeded, clears that
+    /// ping's `Lifetime::Ping` metrics so they are not resent.
+    pub fn process_upload_response(&self, document_id: &str, status: UploadResult) {
+        let uploaded_ping_name = self.write().upload_manager.process_response(document_id, status);
+        if let Some(ping_name) = uploaded_ping_name {
+            self.read().data_store.clear_ping_lifetime_storage(&ping_name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_glean() -> (tempfile::TempDir, Glean) {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let cfg = Configuration {
+            data_path: dir.path().to_str().unwrap().to_string(),
+            application_id: "org.example.test".to_string(),
+            upload_enabled: true,
+            max_events: None,
+            channel: None,
+        };
+        (dir, Glean::new(cfg))
+    }
+
+    #[test]
+    fn client_id_persists_across_restarts() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let cfg = || Configuration {
+            data_path: dir.path().to_str().unwrap().to_string(),
+            application_id: "org.example.test".to_string(),
+            upload_enabled: true,
+            max_events: None,
+            channel: None,
+        };
+
+        let first = Glean::new(cfg());
+        let first_client_id = internal_metrics::client_id.get(&first).expect("client_id to be set");
+
+        let second = Glean::new(cfg());
+        assert_eq!(internal_metrics::client_id.get(&second), Some(first_client_id));
+    }
+
+    #[test]
+    fn set_upload_enabled_false_replaces_client_id_with_canary() {
+        let (_dir, glean) = test_glean();
+
+        let original_client_id = internal_metrics::client_id.get(&glean);
+        assert!(original_client_id.is_some());
+
+        glean.set_upload_enabled(false);
+
+        assert_eq!(
+            internal_metrics::client_id.get(&glean),
+            Some(CANARY_CLIENT_ID.to_string())
+        );
+    }
+
+    #[test]
+    fn dirty_bit_survives_opt_out_wipe() {
+        let (_dir, glean) = test_glean();
+
+        // `set_upload_enabled(false)` clears all of `Lifetime::User`, which
+        // `dirty_bit` shares with `client_id`; it must come back `true`
+        // since this session is still running uncleanly.
+        glean.set_upload_enabled(false);
+
+        assert_eq!(internal_metrics::dirty_bit.get(&glean), Some(true));
+    }
+
+    #[test]
+    fn record_is_a_noop_once_upload_is_disabled() {
+        let (_dir, glean) = test_glean();
+        glean.set_upload_enabled(false);
+
+        glean.record(
+            Lifetime::Application,
+            "metrics",
+            "a.b",
+            &Metric::String("should not be stored".to_string()),
+        );
+
+        let mut seen = Vec::new();
+        glean.iter_store_from(Lifetime::Application, "metrics#", |_, metric| {
+            seen.push(metric.clone());
+        });
+        assert!(seen.is_empty());
+    }
+
+    #[test]
+    fn independently_configured_instances_do_not_share_state() {
+        let dir_a = tempfile::tempdir().expect("Failed to create temp dir");
+        let glean_a = Glean::new(Configuration {
+            data_path: dir_a.path().to_str().unwrap().to_string(),
+            application_id: "org.example.a".to_string(),
+            upload_enabled: true,
+            max_events: None,
+            channel: None,
+        });
+
+        let dir_b = tempfile::tempdir().expect("Failed to create temp dir");
+        let glean_b = Glean::new(Configuration {
+            data_path: dir_b.path().to_str().unwrap().to_string(),
+            application_id: "org.example.b".to_string(),
+            upload_enabled: true,
+            max_events: None,
+            channel: None,
+        });
+
+        glean_a.set_upload_enabled(false);
+
+        assert!(glean_b.is_upload_enabled());
+        assert_ne!(
+            internal_metrics::client_id.get(&glean_a),
+            internal_metrics::client_id.get(&glean_b)
+        );
+        assert_eq!(glean_a.application_id(), "org.example.a");
+        assert_eq!(glean_b.application_id(), "org.example.b");
+    }
+
+    #[test]
+    fn record_resumes_once_upload_is_re_enabled() {
+        let (_dir, glean) = test_glean();
+        glean.set_upload_enabled(false);
+        glean.set_upload_enabled(true);
+
+        glean.record(
+            Lifetime::Application,
+            "metrics",
+            "a.b",
+            &Metric::String("stored".to_string()),
+        );
+
+        let mut seen = None;
+        glean.iter_store_from(Lifetime::Application, "metrics#", |_, metric| {
+            seen = Some(metric.clone());
+        });
+        assert_eq!(seen, Some(Metric::String("stored".to_string())));
+    }
+
+    #[test]
+    fn dirty_bit_does_not_leak_into_baseline_snapshot() {
+        let (_dir, glean) = test_glean();
+
+        let snapshot = crate::storage::StorageManager::snapshot(&glean, "baseline");
+        let dirty_bit_in_baseline = snapshot
+            .get("boolean")
+            .and_then(|b| b.get("dirty_bit"))
+            .is_some();
+        assert!(!dirty_bit_in_baseline);
+    }
+
+    #[test]
+    fn unclean_shutdown_queues_dirty_startup_baseline_ping() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let data_path = dir.path().to_str().unwrap().to_string();
+        let cfg = || Configuration {
+            data_path: data_path.clone(),
+            application_id: "org.example.test".to_string(),
+            upload_enabled: true,
+            max_events: None,
+            channel: None,
+        };
+
+        // First session ends without calling `on_shutdown`, leaving the
+        // dirty bit set.
+        let _first = Glean::new(cfg());
+
+        // A second session against the same data_path should notice and
+        // queue a dirty_startup baseline ping.
+        let second = Glean::new(cfg());
+        match second.get_upload_task() {
+            PingUploadTask::Upload { body, .. } => {
+                assert!(body.contains("dirty_startup"));
+            }
+            other => panic!("expected a queued dirty_startup ping, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn clean_shutdown_does_not_queue_dirty_startup_baseline_ping() {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let data_path = dir.path().to_str().unwrap().to_string();
+        let cfg = || Configuration {
+            data_path: data_path.clone(),
+            application_id: "org.example.test".to_string(),
+            upload_enabled: true,
+            max_events: None,
+            channel: None,
+        };
+
+        let first = Glean::new(cfg());
+        first.on_shutdown();
+
+        let second = Glean::new(cfg());
+        assert_eq!(second.get_upload_task(), PingUploadTask::Done);
+    }
 }
@@ -0,0 +1,53 @@
+use crate::common_metric_data::CommonMetricData;
+use crate::metrics::Metric;
+use crate::Glean;
+
+/// A boolean metric.
+///
+/// Records a single truth value, e.g. "is the user logged in".
+#[derive(Debug, Clone)]
+pub struct BooleanMetric {
+    meta: CommonMetricData,
+}
+
+impl BooleanMetric {
+    /// Creates a new boolean metric.
+    pub fn new(meta: CommonMetricData) -> Self {
+        Self { meta }
+    }
+
+    /// Sets to the specified boolean value.
+    pub fn set(&self, glean: &Glean, value: bool) {
+        if self.meta.disabled {
+            return;
+        }
+
+        let meta = self.meta.clone();
+        glean.record(meta.lifetime, &meta.send_in_pings[0], &meta.identifier(), &Metric::Boolean(value));
+    }
+
+    /// Like [`BooleanMetric::set`], but bypasses the `upload_enabled` gate.
+    /// Reserved for Glean's own lifecycle bookkeeping metrics (see
+    /// `crate::internal_metrics`), which must persist even while upload is
+    /// disabled.
+    pub(crate) fn force_set(&self, glean: &Glean, value: bool) {
+        if self.meta.disabled {
+            return;
+        }
+
+        let meta = self.meta.clone();
+        glean.record_unchecked(meta.lifetime, &meta.send_in_pings[0], &meta.identifier(), &Metric::Boolean(value));
+    }
+
+    /// Gets the currently stored value, if any.
+    pub fn get(&self, glean: &Glean) -> Option<bool> {
+        let key = format!("{}#{}", self.meta.send_in_pings[0], self.meta.identifier());
+        let mut result = None;
+        glean.iter_store_from(self.meta.lifetime, &key, |_, metric| {
+            if let Metric::Boolean(value) = metric {
+                result = Some(*value);
+            }
+        });
+        result
+    }
+}
@@ -0,0 +1,35 @@
+use crate::common_metric_data::CommonMetricData;
+use crate::metrics::Metric;
+use crate::Glean;
+
+/// A counter metric.
+///
+/// Used to count how many times something happens, e.g. the number of times
+/// a certain button was clicked.
+#[derive(Debug, Clone)]
+pub struct CounterMetric {
+    meta: CommonMetricData,
+}
+
+impl CounterMetric {
+    /// Creates a new counter metric.
+    pub fn new(meta: CommonMetricData) -> Self {
+        Self { meta }
+    }
+
+    /// Increases the counter by `amount`.
+    pub fn add(&self, glean: &Glean, amount: i32) {
+        if self.meta.disabled {
+            return;
+        }
+
+        let meta = self.meta.clone();
+        glean.record_with(meta.lifetime, &meta.send_in_pings[0], &meta.identifier(), move |old_value| {
+            let old_value = match old_value {
+                Some(Metric::Counter(i)) => i,
+                _ => 0,
+            };
+            Metric::Counter(old_value.saturating_add(amount))
+        });
+    }
+}
@@ -0,0 +1,50 @@
+use serde_json::Value as JsonValue;
+
+mod boolean;
+mod counter;
+mod string;
+mod uuid;
+
+pub use boolean::BooleanMetric;
+pub use counter::CounterMetric;
+pub use string::StringMetric;
+pub use uuid::UuidMetric;
+
+/// A snapshot of a single metric's recorded value, as kept in the data
+/// store. This is the type that is actually persisted to disk, independent
+/// of which metric type produced it.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Metric {
+    /// A boolean value.
+    Boolean(bool),
+    /// A counter value.
+    Counter(i32),
+    /// A string value.
+    String(String),
+    /// A UUID, stored as its string representation.
+    Uuid(String),
+}
+
+impl Metric {
+    /// The name of the metric-type section this metric is reported under in
+    /// a ping's `metrics` payload (e.g. `"boolean"`, `"string"`).
+    pub fn ping_section(&self) -> &'static str {
+        match self {
+            Metric::Boolean(_) => "boolean",
+            Metric::Counter(_) => "counter",
+            Metric::String(_) => "string",
+            Metric::Uuid(_) => "uuid",
+        }
+    }
+
+    /// Convert this metric's value into the JSON representation used when
+    /// assembling a ping payload.
+    pub fn as_json(&self) -> JsonValue {
+        match self {
+            Metric::Boolean(b) => JsonValue::from(*b),
+            Metric::Counter(c) => JsonValue::from(*c),
+            Metric::String(s) => JsonValue::from(s.clone()),
+            Metric::Uuid(u) => JsonValue::from(u.clone()),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use crate::common_metric_data::CommonMetricData;
+use crate::error_recording::{record_error, ErrorType};
+use crate::metrics::Metric;
+use crate::Glean;
+
+/// The maximum length of a string metric's value, in bytes.
+const MAX_LENGTH_VALUE: usize = 100;
+
+/// A string metric.
+#[derive(Debug, Clone)]
+pub struct StringMetric {
+    meta: CommonMetricData,
+}
+
+impl StringMetric {
+    /// Creates a new string metric.
+    pub fn new(meta: CommonMetricData) -> Self {
+        Self { meta }
+    }
+
+    /// Sets to the specified string value, truncating it if it is too long.
+    pub fn set<S: Into<String>>(&self, glean: &Glean, value: S) {
+        if self.meta.disabled {
+            return;
+        }
+
+        let mut value = value.into();
+        if value.len() > MAX_LENGTH_VALUE {
+            // Truncate at the last char boundary at or before the byte
+            // limit, rather than a raw byte offset, so a multi-byte UTF-8
+            // character straddling the limit isn't split mid-character.
+            let mut boundary = MAX_LENGTH_VALUE;
+            while !value.is_char_boundary(boundary) {
+                boundary -= 1;
+            }
+            value.truncate(boundary);
+            record_error(glean, &self.meta, ErrorType::InvalidOverflow);
+        }
+
+        let meta = self.meta.clone();
+        glean.record(meta.lifetime, &meta.send_in_pings[0], &meta.identifier(), &Metric::String(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Configuration;
+
+    fn test_glean() -> (tempfile::TempDir, Glean) {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let cfg = Configuration {
+            data_path: dir.path().to_str().unwrap().to_string(),
+            application_id: "org.example.test".to_string(),
+            upload_enabled: true,
+            max_events: None,
+            channel: None,
+        };
+        (dir, Glean::new(cfg))
+    }
+
+    #[test]
+    fn truncates_multi_byte_utf8_without_panicking() {
+        let (_dir, glean) = test_glean();
+        let metric = StringMetric::new(CommonMetricData::new("category", "name", "metrics"));
+
+        // 40 three-byte "€" characters is 120 bytes, so the 100-byte limit
+        // falls in the middle of one of them.
+        let value: String = std::iter::repeat('€').take(40).collect();
+        metric.set(&glean, value);
+
+        let stored = metric_value(&glean, "metrics", "category.name");
+        match stored {
+            Some(Metric::String(s)) => assert!(s.len() <= MAX_LENGTH_VALUE),
+            other => panic!("expected a stored string, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn records_invalid_overflow_error_on_truncation() {
+        let (_dir, glean) = test_glean();
+        let metric = StringMetric::new(CommonMetricData::new("category", "name", "metrics"));
+
+        metric.set(&glean, "a".repeat(MAX_LENGTH_VALUE + 1));
+
+        let error_count = metric_value(
+            &glean,
+            "metrics",
+            "glean.error.invalid_overflow/category.name",
+        );
+        assert_eq!(error_count, Some(Metric::Counter(1)));
+    }
+
+    fn metric_value(glean: &Glean, ping_name: &str, key: &str) -> Option<Metric> {
+        let full_key = format!("{}#{}", ping_name, key);
+        let mut result = None;
+        glean.iter_store_from(crate::Lifetime::Ping, &full_key, |_, metric| {
+            result = Some(metric.clone());
+        });
+        result
+    }
+}
@@ -0,0 +1,93 @@
+use uuid::Uuid;
+
+use crate::common_metric_data::CommonMetricData;
+use crate::metrics::Metric;
+use crate::Glean;
+
+/// A UUID metric.
+#[derive(Debug, Clone)]
+pub struct UuidMetric {
+    meta: CommonMetricData,
+}
+
+impl UuidMetric {
+    /// Creates a new UUID metric.
+    pub fn new(meta: CommonMetricData) -> Self {
+        Self { meta }
+    }
+
+    /// Sets to the specified value.
+    pub fn set(&self, glean: &Glean, value: Uuid) {
+        if self.meta.disabled {
+            return;
+        }
+
+        let meta = self.meta.clone();
+        glean.record(
+            meta.lifetime,
+            &meta.send_in_pings[0],
+            &meta.identifier(),
+            &Metric::Uuid(value.to_string()),
+        );
+    }
+
+    /// Like [`UuidMetric::set`], but bypasses the `upload_enabled` gate.
+    /// Reserved for Glean's own lifecycle bookkeeping metrics (see
+    /// `crate::internal_metrics`), which must persist even while upload is
+    /// disabled.
+    pub(crate) fn force_set(&self, glean: &Glean, value: Uuid) {
+        if self.meta.disabled {
+            return;
+        }
+
+        let meta = self.meta.clone();
+        glean.record_unchecked(
+            meta.lifetime,
+            &meta.send_in_pings[0],
+            &meta.identifier(),
+            &Metric::Uuid(value.to_string()),
+        );
+    }
+
+    /// Generates a new random (v4) UUID and sets it.
+    pub fn generate_and_set(&self, glean: &Glean) -> Uuid {
+        let value = Uuid::new_v4();
+        self.set(glean, value);
+        value
+    }
+
+    /// Like [`UuidMetric::generate_and_set`], but bypasses the
+    /// `upload_enabled` gate. See [`UuidMetric::force_set`].
+    pub(crate) fn force_generate_and_set(&self, glean: &Glean) -> Uuid {
+        let value = Uuid::new_v4();
+        self.force_set(glean, value);
+        value
+    }
+
+    /// Sets to the value only if there isn't already a value recorded.
+    pub fn generate_if_missing(&self, glean: &Glean) {
+        if self.get(glean).is_none() {
+            self.generate_and_set(glean);
+        }
+    }
+
+    /// Like [`UuidMetric::generate_if_missing`], but bypasses the
+    /// `upload_enabled` gate. See [`UuidMetric::force_set`].
+    pub(crate) fn force_generate_if_missing(&self, glean: &Glean) {
+        if self.get(glean).is_none() {
+            self.force_generate_and_set(glean);
+        }
+    }
+
+    /// Gets the currently stored value, if any.
+    pub fn get(&self, glean: &Glean) -> Option<String> {
+        let key = format!("{}#{}", self.meta.send_in_pings[0], self.meta.identifier());
+        let mut result = None;
+        glean.iter_store_from(self.meta.lifetime, &key, |_, metric| {
+            if let Metric::Uuid(value) = metric {
+                result = Some(value.clone());
+            }
+        });
+        result
+    }
+}
@@ -0,0 +1,203 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value as JsonValue};
+use uuid::Uuid;
+
+use crate::storage::StorageManager;
+use crate::Glean;
+
+/// The directory (relative to `data_path`) pings are written to once they
+/// are ready to be uploaded, and read back from on startup.
+const PENDING_PINGS_DIR: &str = "pending_pings";
+
+/// A ping that has been assembled and is waiting to be uploaded.
+#[derive(Debug, Clone)]
+pub struct PendingPing {
+    /// The UUID that identifies this ping instance.
+    pub document_id: String,
+    /// The ping's type, e.g. `"baseline"` or `"deletion-request"`.
+    pub ping_name: String,
+    /// The server-relative submission path for this ping.
+    pub path: String,
+    /// The JSON-encoded ping body.
+    pub body: String,
+}
+
+/// Assembles ping payloads and manages their on-disk queue of pending
+/// uploads.
+pub struct PingMaker;
+
+impl PingMaker {
+    /// Collects the current value of every metric queued for `ping_name`
+    /// and assembles it into a ping payload, returning `None` if there is
+    /// nothing to send (no metrics recorded and no explicit `reason`).
+    pub fn collect(glean: &Glean, ping_name: &str, reason: Option<&str>) -> Option<PendingPing> {
+        let metrics = StorageManager::snapshot(glean, ping_name);
+        let has_metrics = metrics.as_object().is_some_and(|m| !m.is_empty());
+        if !has_metrics && reason.is_none() {
+            return None;
+        }
+
+        let mut body = json!({
+            "ping_info": {
+                "seq": 0,
+                "start_time": "",
+                "end_time": "",
+            },
+            "client_info": client_info(glean),
+            "metrics": metrics,
+        });
+        if let Some(reason) = reason {
+            body["ping_info"]["reason"] = json!(reason);
+        }
+        if let Some(channel) = glean.channel() {
+            body["client_info"]["app_channel"] = json!(channel);
+        }
+
+        let document_id = Uuid::new_v4().to_string();
+        let path = format!(
+            "/submit/{}/{}/1/{}",
+            glean.application_id(),
+            ping_name,
+            document_id
+        );
+
+        Some(PendingPing {
+            document_id,
+            ping_name: ping_name.to_string(),
+            path,
+            body: body.to_string(),
+        })
+    }
+
+    /// Assembles a `deletion-request` ping recording the client id that is
+    /// being abandoned, so the server can erase any data submitted under it.
+    ///
+    /// Unlike [`PingMaker::collect`], this does not snapshot the metrics
+    /// store: the whole point is to report a client id that the store no
+    /// longer holds by the time this ping is sent.
+    pub fn collect_deletion_request(glean: &Glean, former_client_id: &str) -> PendingPing {
+        let body = json!({
+            "ping_info": {
+                "seq": 0,
+                "reason": "set_upload_enabled",
+            },
+            "client_info": {
+                "client_id": former_client_id,
+            },
+        });
+
+        let document_id = Uuid::new_v4().to_string();
+        let path = format!(
+            "/submit/{}/deletion-request/1/{}",
+            glean.application_id(),
+            document_id
+        );
+
+        PendingPing {
+            document_id,
+            ping_name: "deletion-request".to_string(),
+            path,
+            body: body.to_string(),
+        }
+    }
+
+    /// Writes an already-assembled ping to the pending pings directory.
+    pub fn store(data_path: &str, pending: &PendingPing) {
+        let dir = pending_pings_dir(data_path);
+        fs::create_dir_all(&dir).expect("Failed to create pending pings directory");
+
+        let file_path = dir.join(&pending.document_id);
+        // `ping_name`, `path` and `body` are newline-separated so
+        // `scan_pending` can read them back without a full JSON parse of
+        // the envelope.
+        let contents = format!("{}\n{}\n{}", pending.ping_name, pending.path, pending.body);
+        fs::write(file_path, contents).expect("Failed to write pending ping");
+    }
+
+    /// Removes a pending ping from disk, e.g. after a successful upload or
+    /// once its retries are exhausted.
+    pub fn remove(data_path: &str, document_id: &str) {
+        let file_path = pending_pings_dir(data_path).join(document_id);
+        let _ = fs::remove_file(file_path);
+    }
+
+    /// Scans the pending pings directory for pings left over from a
+    /// previous run, in no particular order.
+    pub fn scan_pending(data_path: &str) -> Vec<PendingPing> {
+        let dir = pending_pings_dir(data_path);
+        let Ok(entries) = fs::read_dir(&dir) else {
+            return Vec::new();
+        };
+
+        entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let document_id = entry.file_name().to_string_lossy().to_string();
+                let contents = fs::read_to_string(entry.path()).ok()?;
+                let mut parts = contents.splitn(3, '\n');
+                let ping_name = parts.next()?.to_string();
+                let path = parts.next()?.to_string();
+                let body = parts.next()?.to_string();
+                Some(PendingPing {
+                    document_id,
+                    ping_name,
+                    path,
+                    body,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Builds the `client_info` section of a ping payload out of the metrics
+/// recorded under the `"glean_client_info"` pseudo-ping-name (`client_id`,
+/// `first_run`), which every ping reports regardless of which metrics it
+/// was actually collecting.
+fn client_info(glean: &Glean) -> JsonValue {
+    let metrics = StorageManager::snapshot(glean, "glean_client_info");
+    let mut info = json!({});
+    if let Some(client_id) = metrics.get("uuid").and_then(|m| m.get("client_id")) {
+        info["client_id"] = client_id.clone();
+    }
+    if let Some(first_run) = metrics.get("boolean").and_then(|m| m.get("first_run")) {
+        info["first_run"] = first_run.clone();
+    }
+    info
+}
+
+fn pending_pings_dir(data_path: &str) -> PathBuf {
+    Path::new(data_path).join(PENDING_PINGS_DIR)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Configuration;
+
+    fn test_glean() -> (tempfile::TempDir, Glean) {
+        let dir = tempfile::tempdir().expect("Failed to create temp dir");
+        let cfg = Configuration {
+            data_path: dir.path().to_str().unwrap().to_string(),
+            application_id: "org.example.test".to_string(),
+            upload_enabled: true,
+            max_events: None,
+            channel: None,
+        };
+        (dir, Glean::new(cfg))
+    }
+
+    #[test]
+    fn collect_includes_client_id_and_first_run_in_client_info() {
+        let (_dir, glean) = test_glean();
+
+        let pending = PingMaker::collect(&glean, "baseline", Some("test"))
+            .expect("a reason was given, so this should always collect");
+        let body: serde_json::Value =
+            serde_json::from_str(&pending.body).expect("body should be valid JSON");
+
+        assert!(body["client_info"]["client_id"].is_string());
+        assert_eq!(body["client_info"]["first_run"], json!(true));
+    }
+}
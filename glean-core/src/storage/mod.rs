@@ -0,0 +1,33 @@
+use std::collections::HashMap;
+
+use serde_json::{json, Value as JsonValue};
+
+use crate::common_metric_data::Lifetime;
+use crate::Glean;
+
+/// Builds the metrics section of a ping payload by snapshotting everything
+/// recorded for a given ping name out of the data store.
+pub struct StorageManager;
+
+impl StorageManager {
+    /// Collects all metrics scheduled to be sent in `ping_name`, across all
+    /// lifetimes, into the `{"metric_type": {"category.name": value}}` shape
+    /// used in ping payloads.
+    pub fn snapshot(glean: &Glean, ping_name: &str) -> JsonValue {
+        let mut sections: HashMap<&'static str, HashMap<String, JsonValue>> = HashMap::new();
+        let prefix = format!("{}#", ping_name);
+
+        for lifetime in &[Lifetime::Ping, Lifetime::Application, Lifetime::User] {
+            glean.iter_store_from(*lifetime, &prefix, |key, metric| {
+                let key = String::from_utf8_lossy(key);
+                let metric_key = key.trim_start_matches(&prefix).to_string();
+                sections
+                    .entry(metric.ping_section())
+                    .or_insert_with(HashMap::new)
+                    .insert(metric_key, metric.as_json());
+            });
+        }
+
+        json!(sections)
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::ping::{PendingPing, PingMaker};
+
+/// The maximum number of times a ping is retried after a recoverable
+/// failure before it is given up on and dropped.
+const MAX_RETRIES: u32 = 3;
+
+/// How many uploads the rate limiter allows per [`RATE_LIMIT_INTERVAL`].
+const RATE_LIMIT_MAX_TOKENS: u32 = 15;
+
+/// The token bucket's refill interval.
+const RATE_LIMIT_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The base delay before a retried ping becomes eligible for upload again.
+/// Doubled for each retry (1st retry waits `BACKOFF_BASE`, 2nd waits
+/// `2 * BACKOFF_BASE`, etc.), so repeated failures back off exponentially
+/// instead of hammering the server again on the very next `get_task` call.
+const BACKOFF_BASE: Duration = Duration::from_secs(1);
+
+/// A task handed to the embedding layer in response to
+/// [`crate::Glean::get_upload_task`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum PingUploadTask {
+    /// Upload the ping at `path`, with the given `body` and `headers`.
+    Upload {
+        /// The ping's unique identifier, to be echoed back in
+        /// [`crate::Glean::process_upload_response`].
+        document_id: String,
+        /// The server-relative submission path.
+        path: String,
+        /// The JSON-encoded ping body.
+        body: String,
+        /// HTTP headers that should be attached to the upload request.
+        headers: Vec<(String, String)>,
+    },
+    /// There is nothing to upload right now, but there may be again soon.
+    /// The embedder should wait at least `time_ms` before asking again.
+    Wait {
+        /// Suggested delay, in milliseconds, before calling
+        /// `get_upload_task` again.
+        time_ms: u64,
+    },
+    /// There is nothing queued for upload.
+    Done,
+}
+
+/// The outcome of an upload attempt, as reported by the embedding layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UploadResult {
+    /// The HTTP request completed with the given status code.
+    HttpStatus(u16),
+    /// The request could not be completed and should not be retried (e.g. a
+    /// malformed ping was rejected by the server).
+    UnrecoverableFailure,
+    /// The request could not be completed but might succeed if retried
+    /// (e.g. a network error).
+    RecoverableFailure,
+}
+
+impl UploadResult {
+    fn is_success(self) -> bool {
+        matches!(self, UploadResult::HttpStatus(code) if (200..300).contains(&code))
+    }
+
+    fn is_recoverable(self) -> bool {
+        match self {
+            UploadResult::RecoverableFailure => true,
+            UploadResult::HttpStatus(code) => (500..600).contains(&code),
+            UploadResult::UnrecoverableFailure => false,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct QueuedPing {
+    pending: PendingPing,
+    retry_count: u32,
+    /// The ping is not eligible for upload until this point in time, used
+    /// to back off after a recoverable failure.
+    not_before: Instant,
+}
+
+/// The delay before retry number `retry_count` (1-indexed) is eligible for
+/// upload again, growing exponentially.
+fn backoff_for(retry_count: u32) -> Duration {
+    BACKOFF_BASE * 2u32.saturating_pow(retry_count.saturating_sub(1))
+}
+
+/// A simple token-bucket rate limiter used to smooth out upload bursts.
+#[derive(Debug)]
+struct RateLimiter {
+    max_tokens: u32,
+    interval: Duration,
+    tokens: u32,
+    window_start: Instant,
+}
+
+impl RateLimiter {
+    fn new(max_tokens: u32, interval: Duration) -> Self {
+        Self {
+            max_tokens,
+            interval,
+            tokens: max_tokens,
+            window_start: Instant::now(),
+        }
+    }
+
+    /// Tries to take a single token. Returns `Ok(())` if one was available,
+    /// or `Err(remaining)` with the time until the next refill otherwise.
+    fn take(&mut self) -> Result<(), Duration> {
+        let elapsed = self.window_start.elapsed();
+        if elapsed >= self.interval {
+            self.tokens = self.max_tokens;
+            self.window_start = Instant::now();
+        }
+
+        if self.tokens > 0 {
+            self.tokens -= 1;
+            Ok(())
+        } else {
+            Err(self.interval.saturating_sub(elapsed))
+        }
+    }
+}
+
+/// Owns the queue of pings waiting to be uploaded and decides, via
+/// [`get_task`](PingUploadManager::get_task), what the embedder should do
+/// next. This keeps upload policy (retries, rate limiting, persistence)
+/// separate from the recording path.
+#[derive(Debug)]
+pub struct PingUploadManager {
+    data_path: String,
+    queue: VecDeque<QueuedPing>,
+    /// Pings handed out via `get_task` that are awaiting a response, keyed
+    /// by document id, so `process_response` can look up their retry count.
+    in_flight: HashMap<String, QueuedPing>,
+    rate_limiter: RateLimiter,
+}
+
+impl PingUploadManager {
+    /// Creates a new manager, restoring any pings left pending from a
+    /// previous run by scanning `data_path`'s pending-pings directory.
+    pub fn new(data_path: &str) -> Self {
+        let queue = PingMaker::scan_pending(data_path)
+            .into_iter()
+            .map(|pending| QueuedPing {
+                pending,
+                retry_count: 0,
+                not_before: Instant::now(),
+            })
+            .collect();
+
+        Self {
+            data_path: data_path.to_string(),
+            queue,
+            in_flight: HashMap::new(),
+            rate_limiter: RateLimiter::new(RATE_LIMIT_MAX_TOKENS, RATE_LIMIT_INTERVAL),
+        }
+    }
+
+    /// Queues `pending` for upload, persisting it to disk first so it is
+    /// not lost if the process exits before it is sent.
+    pub fn enqueue(&mut self, pending: PendingPing) {
+        PingMaker::store(&self.data_path, &pending);
+        self.queue.push_back(QueuedPing {
+            pending,
+            retry_count: 0,
+            not_before: Instant::now(),
+        });
+    }
+
+    /// Returns the next task for the embedder to act on.
+    pub fn get_task(&mut self) -> PingUploadTask {
+        let Some(front) = self.queue.front() else {
+            return PingUploadTask::Done;
+        };
+
+        let now = Instant::now();
+        if front.not_before > now {
+            return PingUploadTask::Wait {
+                time_ms: (front.not_before - now).as_millis() as u64,
+            };
+        }
+
+        match self.rate_limiter.take() {
+            Ok(()) => {
+                let queued = self.queue.pop_front().expect("queue checked non-empty above");
+                let task = PingUploadTask::Upload {
+                    document_id: queued.pending.document_id.clone(),
+                    path: queued.pending.path.clone(),
+                    body: queued.pending.body.clone(),
+                    headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+                };
+                self.in_flight.insert(queued.pending.document_id.clone(), queued);
+                task
+            }
+            Err(remaining) => PingUploadTask::Wait {
+                time_ms: remaining.as_millis() as u64,
+            },
+        }
+    }
+
+    /// Records the outcome of an upload attempt for `document_id`.
+    ///
+    /// On success, the ping is dropped and removed from disk, and its
+    /// `ping_name` is returned so the caller can clear that ping's
+    /// `Lifetime::Ping` data now that it has been sent. On an
+    /// unrecoverable/4xx failure, the ping is dropped without being
+    /// reported. On a recoverable failure (or 5xx) it is re-enqueued,
+    /// backing off exponentially, up to [`MAX_RETRIES`] times, after which
+    /// it is also dropped.
+    pub fn process_response(&mut self, document_id: &str, status: UploadResult) -> Option<String> {
+        let mut queued = self.in_flight.remove(document_id)?;
+
+        if status.is_success() {
+            PingMaker::remove(&self.data_path, document_id);
+            return Some(queued.pending.ping_name);
+        }
+
+        if status.is_recoverable() && queued.retry_count < MAX_RETRIES {
+            queued.retry_count += 1;
+            queued.not_before = Instant::now() + backoff_for(queued.retry_count);
+            self.queue.push_back(queued);
+            return None;
+        }
+
+        PingMaker::remove(&self.data_path, document_id);
+        None
+    }
+}